@@ -12,6 +12,7 @@ extern crate tokio_core;
 extern crate tokio_process;
 extern crate tokio_io;
 extern crate futures;
+extern crate cargo_metadata;
 #[macro_use]
 extern crate error_chain;
 
@@ -48,7 +49,9 @@ fn main() {
         .subcommand(SubCommand::with_name("init").about("Initialize the fuzz folder")
             .arg(Arg::with_name("target").long("target").short("t").required(false)
                  .default_value("fuzzer_script_1")
-                 .help("name of the first fuzz target to create")))
+                 .help("name of the first fuzz target to create"))
+            .arg(Arg::with_name("package").long("package").short("p").takes_value(true)
+                 .help("package to fuzz, if the project is a workspace")))
         .subcommand(SubCommand::with_name("run").long_about("Run the fuzz target in fuzz/fuzzers")
             .about(
 "
@@ -72,6 +75,15 @@ this will run indefinitely.")
                  .possible_values(&["address", "leak", "memory", "thread"])
                  .default_value("address")
                  .help("Use different sanitizer"))
+            .arg(Arg::with_name("engine").long("engine")
+                 .takes_value(true)
+                 .possible_values(&["libfuzzer", "honggfuzz"])
+                 .default_value("libfuzzer")
+                 .help("Fuzzing engine to build and run the target with"))
+            .arg(Arg::with_name("no_cfg_fuzzing").long("no-cfg-fuzzing")
+                 .help("Don't pass --cfg fuzzing to rustc when building the target"))
+            .arg(Arg::with_name("package").long("package").short("p").takes_value(true)
+                 .help("package to fuzz, if the project is a workspace"))
             .arg(Arg::with_name("TARGET").required(true)
                  .help("name of the fuzz target"))
             .arg(Arg::with_name("CORPUS").multiple(true)
@@ -105,17 +117,134 @@ Some useful options (to be used as `cargo fuzz run fuzz_target -- <options>`) in
         .subcommand(SubCommand::with_name("add").about("Add a new fuzz target")
                     .arg(Arg::with_name("TARGET").required(true)
                          .help("name of the fuzz target"))
+                    .arg(Arg::with_name("package").long("package").short("p").takes_value(true)
+                         .help("package to fuzz, if the project is a workspace"))
+        )
+        .subcommand(SubCommand::with_name("list").about("List all fuzz targets")
+                    .arg(Arg::with_name("package").long("package").short("p").takes_value(true)
+                         .help("package to fuzz, if the project is a workspace"))
+                    .arg(Arg::with_name("crashes").long("crashes")
+                         .help("list stored crash reproducers instead of target names"))
+                    .arg(Arg::with_name("hangs").long("hangs").conflicts_with("crashes")
+                         .help("list stored hang reproducers instead of target names"))
+                    .arg(Arg::with_name("TARGET").required(false)
+                         .help("only list reproducers for this target"))
+        )
+        .subcommand(SubCommand::with_name("cov").about("Generate a coverage report for a corpus")
+            .long_about(
+"Replay a target's corpus against a build instrumented for LLVM source-based \
+coverage, then render the merged profile with llvm-cov. Requires `llvm-profdata` \
+and `llvm-cov` (e.g. via `rustup component add llvm-tools-preview`) on the PATH.")
+            .arg(Arg::with_name("release").long("release").short("O")
+                 .help("Build artifacts in release mode, with optimizations"))
+            .arg(Arg::with_name("TARGET").required(true)
+                 .help("name of the fuzz target"))
+            .arg(Arg::with_name("CORPUS").multiple(true)
+                 .help("custom corpus directory to replay"))
+            .arg(Arg::with_name("TRIPLE").long("target")
+                 .default_value(utils::default_target())
+                 .help("target triple of the fuzz target"))
+            .arg(Arg::with_name("html").long("html")
+                 .help("emit an HTML line-coverage report instead of the text report"))
+            .arg(Arg::with_name("summary_only").long("summary-only")
+                 .help("only print per-file summaries, skipping per-line coverage"))
+            .arg(Arg::with_name("ignore_filename_regex").long("ignore-filename-regex")
+                 .takes_value(true)
+                 .help("skip source files whose path matches this regex, e.g. the fuzz harness itself"))
+            .arg(Arg::with_name("no_cfg_fuzzing").long("no-cfg-fuzzing")
+                 .help("Don't pass --cfg fuzzing to rustc when building the target"))
+        )
+        .subcommand(SubCommand::with_name("cmin").about("Minimize a target's corpus")
+            .arg(Arg::with_name("release").long("release").short("O")
+                 .help("Build artifacts in release mode, with optimizations"))
+            .arg(Arg::with_name("debug_assertions").long("debug-assertions").short("a")
+                 .help("Build artifacts with debug assertions enabled (default if not -O)"))
+            .arg(Arg::with_name("sanitizer").long("sanitizer").short("s")
+                 .takes_value(true)
+                 .possible_values(&["address", "leak", "memory", "thread"])
+                 .default_value("address")
+                 .help("Use different sanitizer"))
+            .arg(Arg::with_name("TARGET").required(true)
+                 .help("name of the fuzz target"))
+            .arg(Arg::with_name("TRIPLE").long("target")
+                 .default_value(utils::default_target())
+                 .help("target triple of the fuzz target"))
+            .arg(Arg::with_name("no_cfg_fuzzing").long("no-cfg-fuzzing")
+                 .help("Don't pass --cfg fuzzing to rustc when building the target"))
         )
-        .subcommand(SubCommand::with_name("list").about("List all fuzz targets"));
+        .subcommand(SubCommand::with_name("tmin").about("Minimize a crashing input")
+            .arg(Arg::with_name("release").long("release").short("O")
+                 .help("Build artifacts in release mode, with optimizations"))
+            .arg(Arg::with_name("debug_assertions").long("debug-assertions").short("a")
+                 .help("Build artifacts with debug assertions enabled (default if not -O)"))
+            .arg(Arg::with_name("sanitizer").long("sanitizer").short("s")
+                 .takes_value(true)
+                 .possible_values(&["address", "leak", "memory", "thread"])
+                 .default_value("address")
+                 .help("Use different sanitizer"))
+            .arg(Arg::with_name("TARGET").required(true)
+                 .help("name of the fuzz target"))
+            .arg(Arg::with_name("ARTIFACT").required(true)
+                 .help("path to the crashing input to minimize"))
+            .arg(Arg::with_name("TRIPLE").long("target")
+                 .default_value(utils::default_target())
+                 .help("target triple of the fuzz target"))
+            .arg(Arg::with_name("runs").long("runs")
+                 .takes_value(true)
+                 .default_value("1000")
+                 .help("number of minimization attempts to make before giving up"))
+            .arg(Arg::with_name("no_cfg_fuzzing").long("no-cfg-fuzzing")
+                 .help("Don't pass --cfg fuzzing to rustc when building the target"))
+        )
+        .subcommand(SubCommand::with_name("reproduce").about("Run a single reproducer through a target")
+            .arg(Arg::with_name("release").long("release").short("O")
+                 .help("Build artifacts in release mode, with optimizations"))
+            .arg(Arg::with_name("debug_assertions").long("debug-assertions").short("a")
+                 .help("Build artifacts with debug assertions enabled (default if not -O)"))
+            .arg(Arg::with_name("sanitizer").long("sanitizer").short("s")
+                 .takes_value(true)
+                 .possible_values(&["address", "leak", "memory", "thread"])
+                 .default_value("address")
+                 .help("Use different sanitizer"))
+            .arg(Arg::with_name("engine").long("engine")
+                 .takes_value(true)
+                 .possible_values(&["libfuzzer", "honggfuzz"])
+                 .default_value("libfuzzer")
+                 .help("Fuzzing engine the target was built and crashed with"))
+            .arg(Arg::with_name("TARGET").required(true)
+                 .help("name of the fuzz target"))
+            .arg(Arg::with_name("ARTIFACT").required(true)
+                 .help("path to the crash or hang reproducer to run"))
+            .arg(Arg::with_name("TRIPLE").long("target")
+                 .default_value(utils::default_target())
+                 .help("target triple of the fuzz target"))
+            .arg(Arg::with_name("no_cfg_fuzzing").long("no-cfg-fuzzing")
+                 .help("Don't pass --cfg fuzzing to rustc when building the target"))
+        );
     let args = app.get_matches();
 
     process::exit(match args.subcommand() {
         ("init", matches) => FuzzProject::init(matches.expect("arguments present")).map(|_| ()),
-        ("add", matches) =>
-            FuzzProject::new().and_then(|p| p.add_target(matches.expect("arguments present"))),
-        ("list", _) => FuzzProject::new().and_then(|p| p.list_targets()),
-        ("run", matches) =>
-            FuzzProject::new().and_then(|p| p.exec_target(matches.expect("arguments present"))),
+        ("add", matches) => {
+            let matches = matches.expect("arguments present");
+            FuzzProject::new(matches.value_of("package")).and_then(|p| p.add_target(matches))
+        }
+        ("list", matches) => {
+            let matches = matches.expect("arguments present");
+            FuzzProject::new(matches.value_of("package")).and_then(|p| p.list_targets(matches))
+        }
+        ("run", matches) => {
+            let matches = matches.expect("arguments present");
+            FuzzProject::new(matches.value_of("package")).and_then(|p| p.exec_target(matches))
+        }
+        ("cov", matches) =>
+            FuzzProject::new(None).and_then(|p| p.cov_target(matches.expect("arguments present"))),
+        ("cmin", matches) =>
+            FuzzProject::new(None).and_then(|p| p.cmin_target(matches.expect("arguments present"))),
+        ("tmin", matches) =>
+            FuzzProject::new(None).and_then(|p| p.tmin_target(matches.expect("arguments present"))),
+        ("reproduce", matches) =>
+            FuzzProject::new(None).and_then(|p| p.reproduce_target(matches.expect("arguments present"))),
         (s, _) => panic!("unimplemented subcommand {}!", s),
     }.map(|_| 0).unwrap_or_else(|err| {
         utils::report_error(&err);
@@ -123,19 +252,72 @@ Some useful options (to be used as `cargo fuzz run fuzz_target -- <options>`) in
     }));
 }
 
+/// Which fuzzing engine to build and run a target with
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Engine {
+    LibFuzzer,
+    Honggfuzz,
+}
+
+impl Engine {
+    fn from_arg(s: &str) -> Engine {
+        match s {
+            "honggfuzz" => Engine::Honggfuzz,
+            _ => Engine::LibFuzzer,
+        }
+    }
+
+    /// Directory under `fuzz/` holding this engine's corpora (also nested under `crashes/`
+    /// for honggfuzz), so that libFuzzer and honggfuzz don't clobber each other's state
+    /// for the same target
+    fn subdir(&self) -> &'static str {
+        match *self {
+            Engine::LibFuzzer => "corpus",
+            Engine::Honggfuzz => "hfuzz_workspace",
+        }
+    }
+}
+
+/// `RUSTFLAGS` for honggfuzz's sancov-based instrumentation, shared by `run --engine
+/// honggfuzz` and `reproduce --engine honggfuzz`
+fn honggfuzz_rustflags(assertions: bool, cfg_fuzzing: bool) -> String {
+    let other_flags = env::var("RUSTFLAGS").unwrap_or_default();
+    let mut rustflags = String::from(
+        "-Cpasses=sancov \
+         -Cllvm-args=-sanitizer-coverage-level=4 \
+         -Cllvm-args=-sanitizer-coverage-trace-pc-guard \
+         -Clink-dead-code"
+    );
+    if assertions {
+        rustflags.push_str(" -Cdebug-assertions");
+    }
+    if cfg_fuzzing {
+        rustflags.push_str(" --cfg fuzzing");
+    }
+    if !other_flags.is_empty() {
+        rustflags.push_str(" ");
+        rustflags.push_str(&other_flags);
+    }
+    rustflags
+}
+
 struct FuzzProject {
     /// Path to the root cargo project
     ///
     /// Not the project with fuzz targets, but the project being fuzzed
     root_project: path::PathBuf,
+    /// Name of the selected package, as resolved by `cargo metadata`
+    root_project_name: String,
     targets: Vec<String>,
 }
 
 impl FuzzProject {
-    fn new() -> Result<Self> {
+    fn new(package: Option<&str>) -> Result<Self> {
+        let (root_project, root_project_name) = find_package(package)?;
         let mut project = FuzzProject {
-            root_project: find_package()?,
-            targets: Vec::new()
+            root_project,
+            root_project_name,
+            targets: Vec::new(),
         };
         let manifest = project.manifest()?;
         if !is_fuzz_manifest(&manifest) {
@@ -152,12 +334,14 @@ impl FuzzProject {
     ///
     /// This will not clone libfuzzer-sys
     fn init(args: &ArgMatches) -> Result<Self> {
+        let package = args.value_of("package");
+        let (root_project, root_project_name) = find_package(package)?;
         let project = FuzzProject {
-            root_project: find_package()?,
+            root_project,
+            root_project_name: root_project_name.clone(),
             targets: Vec::new(),
         };
         let fuzz_project = project.path();
-        let root_project_name = try!(project.root_project_name());
         let target: String = args.value_of_os("target").expect("target shoud have a default value").to_os_string()
             .into_string().map_err(|_| "target must be valid unicode")?;
 
@@ -176,9 +360,54 @@ impl FuzzProject {
         Ok(project)
     }
 
-    fn list_targets(&self) -> Result<()> {
-        for bin in &self.targets {
-            utils::print_message(bin, term::color::GREEN);
+    fn list_targets(&self, args: &ArgMatches) -> Result<()> {
+        if !args.is_present("crashes") && !args.is_present("hangs") {
+            for bin in &self.targets {
+                utils::print_message(bin, term::color::GREEN);
+            }
+            return Ok(());
+        }
+
+        let only_target = args.value_of("TARGET");
+        let targets: Vec<&String> = self
+            .targets
+            .iter()
+            .filter(|t| only_target.map_or(true, |only| only == t.as_str()))
+            .collect();
+
+        for target in targets {
+            if args.is_present("crashes") {
+                for engine in &[Engine::LibFuzzer, Engine::Honggfuzz] {
+                    self.list_reproducers(target, self.crashes_for(target, *engine)?, |name| {
+                        !name.starts_with("timeout-")
+                    })?;
+                }
+            } else {
+                self.list_reproducers(target, self.hangs_for(target)?, |_| true)?;
+                // The common single-job `run` path replaces the process image via `exec`,
+                // so it never gets a chance to move `timeout-*` artifacts out of the
+                // crashes directory; fall back to scanning there too, for both engines.
+                for engine in &[Engine::LibFuzzer, Engine::Honggfuzz] {
+                    self.list_reproducers(target, self.crashes_for(target, *engine)?, |name| {
+                        name.starts_with("timeout-")
+                    })?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn list_reproducers(&self, target: &str, dir: path::PathBuf, keep: impl Fn(&str) -> bool) -> Result<()> {
+        let entries = fs::read_dir(&dir).chain_err(|| format!("could not read directory {:?}", dir))?;
+        for entry in entries {
+            let entry = entry.chain_err(|| format!("could not read an entry of {:?}", dir))?;
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+            if name.is_empty() || !keep(&name) {
+                continue;
+            }
+            let size = entry.metadata().chain_err(|| format!("could not stat {:?}", entry.path()))?.len();
+            utils::print_message(&format!("{}: {} ({} bytes)", target, name, size), term::color::GREEN);
         }
         Ok(())
     }
@@ -187,8 +416,8 @@ impl FuzzProject {
         let target: String = args.value_of_os("TARGET").expect("TARGET is required").to_os_string()
             .into_string().map_err(|_| "TARGET must be valid unicode")?;
         // Create corpus and artifact directories for the newly added target
-        self.corpus_for(&target)?;
-        self.artifacts_for(&target)?;
+        self.corpus_for(&target, Engine::LibFuzzer)?;
+        self.crashes_for(&target, Engine::LibFuzzer)?;
         self.create_target_template(&target)
             .chain_err(|| format!("could not add target {:?}", target))
     }
@@ -198,26 +427,52 @@ impl FuzzProject {
         let target_path = self.target_path(target);
         let mut script = fs::OpenOptions::new().write(true).create_new(true).open(&target_path)
             .chain_err(|| format!("could not create target script file at {:?}", target_path))?;
-        script.write_fmt(target_template!(self.root_project_name()?.replace("-", "_")))?;
+        script.write_fmt(target_template!(self.root_project_name.replace("-", "_")))?;
 
         let mut cargo = fs::OpenOptions::new().append(true)
             .open(self.manifest_path())?;
         Ok(cargo.write_fmt(toml_bin_template!(target))?)
     }
 
-    /// Fuzz a given fuzz target
-    fn exec_target<'a>(&self, args: &ArgMatches<'a>) -> Result<()> {
-        let target: String = args.value_of_os("TARGET").expect("TARGET is required").to_os_string()
-            .into_string().map_err(|_| "TARGET must be valid unicode")?;
-        let release: bool = args.is_present("release");
-        let assertions: bool = args.is_present("debug_assertions");
-        let sanitizer: &str = args.value_of("sanitizer").expect("no sanitizer");
-        let corpus = args.values_of_os("CORPUS");
-        let exec_args = args.values_of_os("ARGS")
-                            .map(|v| v.collect::<Vec<_>>());
-        let target_triple = args.value_of_os("TRIPLE").expect("no triple");
-        let jobs: u16 = args.value_of("JOBS").expect("no triple").parse().expect("validation");
+    /// Build `target` with the given `RUSTFLAGS` and any other environment variables,
+    /// returning the `cargo_args` used so a caller can issue a follow-up `cargo` command
+    /// (e.g. `run`) against the same build
+    ///
+    /// Shared by every build path (`run`, `cov`, `cmin`, `tmin`, `reproduce`) so a fix to
+    /// the `--manifest-path`/`--target` handling only has to happen once.
+    fn build_instrumented(&self, target: &str, release: bool, target_triple: &OsStr,
+                          envs: &[(&str, String)]) -> Result<Vec<ffi::OsString>> {
+        let manifest_path = self.manifest_path();
+        let mut cargo_args: Vec<ffi::OsString> = Vec::new();
+        cargo_args.push("--manifest-path".into());
+        cargo_args.push(manifest_path.into_os_string());
+        if release {
+            cargo_args.push("--release".into());
+        }
+        cargo_args.push("--verbose".into());
+        cargo_args.push(ffi::OsString::from("--bin"));
+        cargo_args.push(ffi::OsString::from(target));
+        //--target=<TARGET> won't pass rustflags to build scripts
+        cargo_args.push("--target".into());
+        cargo_args.push(target_triple.to_os_string());
 
+        let mut cmd = process::Command::new("cargo");
+        cmd.arg("build").args(&cargo_args);
+        for &(k, ref v) in envs { cmd.env(k, v); } // Command::envs still unstable
+        let status = cmd.status().chain_err(|| format!("could not execute: {:?}", cmd))?;
+        if !status.success() {
+            return Err(format!("could not build fuzz target {:?}: {:?}", target, cmd).into());
+        }
+        Ok(cargo_args)
+    }
+
+    /// Build a target for the libFuzzer engine and return a `cargo run` command
+    /// ready to have its post-build libFuzzer arguments appended
+    ///
+    /// Shared by `run`, `cmin`, `tmin` and `reproduce`, which only differ in the arguments they
+    /// pass to the built binary after `--`.
+    fn build_libfuzzer(&self, target: &str, release: bool, assertions: bool, sanitizer: &str,
+                        cfg_fuzzing: bool, target_triple: &OsStr) -> Result<process::Command> {
         let other_flags = env::var("RUSTFLAGS").unwrap_or_default();
         let mut rustflags: String = format!(
             "-Cpasses=sancov \
@@ -230,18 +485,17 @@ impl FuzzProject {
         if assertions {
             rustflags.push_str(" -Cdebug-assertions");
         }
+        if cfg_fuzzing {
+            // RUSTFLAGS applies to every crate built for the target, not just the fuzz
+            // harness, so dependencies can gate on #[cfg(fuzzing)] too.
+            rustflags.push_str(" --cfg fuzzing");
+        }
         if !other_flags.is_empty() {
             rustflags.push_str(" ");
             rustflags.push_str(&other_flags);
         }
 
-        let manifest_path = self.manifest_path();
-        let mut artefact_arg = ffi::OsString::from("-artifact_prefix=");
-        artefact_arg.push(self.artifacts_for(&target)?);
-
-        let mut cargo_args: Vec<&OsStr> = Vec::new();
         let mut envs = Vec::new();
-
         envs.push(("RUSTFLAGS", rustflags));
 
         // For asan and tsan we have default options. Merge them to the given options,
@@ -269,45 +523,93 @@ impl FuzzProject {
             _ => {}
         }
 
-        cargo_args.push("--manifest-path".as_ref());
-        cargo_args.push(manifest_path.as_ref());
-        if release {
-            cargo_args.push("--release".as_ref());
-        }
-        cargo_args.push("--verbose".as_ref());
-        cargo_args.push("--bin".as_ref());
-        cargo_args.push(&target.as_ref());
-        //--target=<TARGET> won't pass rustflags to build scripts
-        cargo_args.push("--target".as_ref());
-        cargo_args.push(target_triple.as_ref());
+        let cargo_args = self.build_instrumented(target, release, target_triple, &envs)?;
 
         let mut cmd = process::Command::new("cargo");
-        cmd.arg("build")
-           .args(&cargo_args);
+        cmd.arg("run").args(&cargo_args);
         for &(ref k, ref v) in &envs { cmd.env(k, v); } // Command::envs still unstable
-        let status = cmd.status().chain_err(|| format!("could not execute: {:?}", cmd))?;
-        if !status.success() {
-            return Err(format!("could not build fuzz script: {:?}", cmd).into());
-        }
+        Ok(cmd)
+    }
 
-        let mut cmd = process::Command::new("cargo");
-        cmd.arg("run")
-           .args(&cargo_args);
-        for &(ref k, ref v) in &envs { cmd.env(k, v); } // Command::envs still unstable
+    /// Fuzz a given fuzz target
+    fn exec_target<'a>(&self, args: &ArgMatches<'a>) -> Result<()> {
+        let target: String = args.value_of_os("TARGET").expect("TARGET is required").to_os_string()
+            .into_string().map_err(|_| "TARGET must be valid unicode")?;
+        let release: bool = args.is_present("release");
+        let assertions: bool = args.is_present("debug_assertions");
+        let sanitizer: &str = args.value_of("sanitizer").expect("no sanitizer");
+        let engine = Engine::from_arg(args.value_of("engine").expect("no engine"));
+        let corpus = args.values_of_os("CORPUS");
+        let exec_args = args.values_of_os("ARGS")
+                            .map(|v| v.collect::<Vec<_>>());
+        let target_triple = args.value_of_os("TRIPLE").expect("no triple");
+        let jobs: u16 = args.value_of("JOBS").expect("no triple").parse().expect("validation");
+        let cfg_fuzzing = !args.is_present("no_cfg_fuzzing");
 
-        cmd.arg("--");
-        cmd.arg(artefact_arg);
-        if let Some(args) = exec_args {
-            for arg in args {
-                cmd.arg(arg);
+        let mut cmd = match engine {
+            Engine::LibFuzzer => {
+                let mut cmd = self.build_libfuzzer(&target, release, assertions, sanitizer, cfg_fuzzing, target_triple)?;
+
+                let mut artefact_arg = ffi::OsString::from("-artifact_prefix=");
+                artefact_arg.push(self.crashes_for(&target, engine)?);
+
+                cmd.arg("--");
+                cmd.arg(artefact_arg);
+                if let Some(args) = exec_args {
+                    for arg in args {
+                        cmd.arg(arg);
+                    }
+                }
+                if let Some(corpus) = corpus {
+                    for arg in corpus {
+                        cmd.arg(arg);
+                    }
+                } else {
+                    cmd.arg(self.corpus_for(&target, engine)?);
+                }
+                cmd
             }
-        }
-        if let Some(corpus) = corpus {
-            for arg in corpus {
-                cmd.arg(arg);
+
+            Engine::Honggfuzz => {
+                // honggfuzz is an external driver: run the instrumented binary under it
+                // in persistent mode rather than executing the binary directly.
+                let rustflags = honggfuzz_rustflags(assertions, cfg_fuzzing);
+                self.build_instrumented(&target, release, target_triple, &[("RUSTFLAGS", rustflags.clone())])?;
+
+                let input_dir = match corpus {
+                    Some(corpus) => {
+                        let mut corpus: Vec<_> = corpus.collect();
+                        if corpus.len() > 1 {
+                            return Err("honggfuzz only accepts a single corpus directory".into());
+                        }
+                        match corpus.pop() {
+                            Some(dir) => path::PathBuf::from(dir),
+                            None => self.corpus_for(&target, engine)?,
+                        }
+                    }
+                    None => self.corpus_for(&target, engine)?,
+                };
+
+                let mut cmd = process::Command::new("honggfuzz");
+                cmd.env("RUSTFLAGS", &rustflags);
+                cmd.arg("--persistent");
+                cmd.arg("--input").arg(input_dir);
+                cmd.arg("--workspace").arg(self.crashes_for(&target, engine)?);
+                cmd.arg("--threads").arg(jobs.to_string());
+                if let Some(args) = exec_args {
+                    for arg in args {
+                        cmd.arg(arg);
+                    }
+                }
+                cmd.arg("--");
+                cmd.arg(self.bin_path(&target, target_triple, release));
+                cmd
             }
-        } else {
-            cmd.arg(self.corpus_for(&target)?);
+        };
+
+        if engine == Engine::Honggfuzz {
+            // honggfuzz already drives its own `--threads` workers, so never fork jobs copies.
+            return exec_cmd(&mut cmd).chain_err(|| format!("could not execute command: {:?}", cmd)).map(|_| ());
         }
 
         if jobs == 1 {
@@ -349,11 +651,230 @@ impl FuzzProject {
             let (jobnum, _, _) = core.run(exits.join3(stdouts, stderrs))
                 .chain_err(|| format!("could not run the processes: {:?}", cmd))?;
             println!("Worker {} finished fuzzing", jobnum);
+
+            // We regain control here (unlike the single-job path above, which `exec`s
+            // and never returns), so sort libFuzzer's `timeout-*` artifacts out of the
+            // crashes directory and into `hangs_for` where they belong.
+            self.triage_hangs(&target)?;
             Ok(())
         }
 
     }
 
+    /// Replay a target's corpus against an `-Cinstrument-coverage` build and render
+    /// the resulting profile with `llvm-profdata`/`llvm-cov`
+    fn cov_target<'a>(&self, args: &ArgMatches<'a>) -> Result<()> {
+        let target: String = args.value_of_os("TARGET").expect("TARGET is required").to_os_string()
+            .into_string().map_err(|_| "TARGET must be valid unicode")?;
+        let release: bool = args.is_present("release");
+        let corpus = args.values_of_os("CORPUS");
+        let target_triple = args.value_of_os("TRIPLE").expect("no triple");
+        let html: bool = args.is_present("html");
+        let summary_only: bool = args.is_present("summary_only");
+        let ignore_filename_regex = args.value_of("ignore_filename_regex");
+        let cfg_fuzzing = !args.is_present("no_cfg_fuzzing");
+
+        let other_flags = env::var("RUSTFLAGS").unwrap_or_default();
+        let mut rustflags = String::from("-Cinstrument-coverage");
+        if cfg_fuzzing {
+            // Match the RUSTFLAGS the corpus was fuzzed with, so harness code gated on
+            // #[cfg(fuzzing)] runs the same way during replay as it did while fuzzing.
+            rustflags.push_str(" --cfg fuzzing");
+        }
+        if !other_flags.is_empty() {
+            rustflags.push_str(" ");
+            rustflags.push_str(&other_flags);
+        }
+
+        let cargo_args = self.build_instrumented(&target, release, target_triple, &[("RUSTFLAGS", rustflags.clone())])?;
+
+        let coverage_dir = self.coverage_for(&target)?;
+        let corpus_dir = match corpus.and_then(|mut corpus| corpus.next()) {
+            Some(corpus) => path::PathBuf::from(corpus),
+            None => self.corpus_for(&target, Engine::LibFuzzer)?,
+        };
+
+        // libFuzzer writes one profile per process; `-runs=0` just loads and replays
+        // every file in the corpus directory once, then exits.
+        let mut cmd = process::Command::new("cargo");
+        cmd.arg("run").args(&cargo_args)
+           .env("RUSTFLAGS", &rustflags)
+           .env("LLVM_PROFILE_FILE", coverage_dir.join("%p.profraw"));
+        cmd.arg("--");
+        cmd.arg("-runs=0");
+        cmd.arg(&corpus_dir);
+        let status = cmd.status().chain_err(|| format!("could not execute: {:?}", cmd))?;
+        if !status.success() {
+            return Err(format!("could not replay corpus for coverage: {:?}", cmd).into());
+        }
+
+        let profdata_path = coverage_dir.join(format!("{}.profdata", target));
+        let mut merge = process::Command::new("llvm-profdata");
+        merge.arg("merge").arg("-sparse").arg("-o").arg(&profdata_path);
+        for entry in fs::read_dir(&coverage_dir)
+            .chain_err(|| format!("could not read coverage directory {:?}", coverage_dir))? {
+            let entry = entry?;
+            if entry.path().extension().map_or(false, |ext| ext == "profraw") {
+                merge.arg(entry.path());
+            }
+        }
+        let status = merge.status().chain_err(|| format!("could not execute: {:?}", merge))?;
+        if !status.success() {
+            return Err(format!("could not merge coverage profiles: {:?}", merge).into());
+        }
+
+        let bin_path = self.bin_path(&target, target_triple, release);
+        let mut cov = process::Command::new("llvm-cov");
+        cov.arg(if html { "show" } else { "report" });
+        cov.arg(format!("--instr-profile={}", profdata_path.display()));
+        cov.arg("-Xdemangler=rustfilt");
+        if html {
+            cov.arg("--format=html");
+            cov.arg(format!("--output-dir={}", coverage_dir.join("report").display()));
+        }
+        if summary_only {
+            cov.arg("--summary-only");
+        }
+        if let Some(ignore_filename_regex) = ignore_filename_regex {
+            cov.arg("--ignore-filename-regex").arg(ignore_filename_regex);
+        }
+        cov.arg(&bin_path);
+        exec_cmd(&mut cov).chain_err(|| format!("could not execute command: {:?}", cov))?;
+        Ok(())
+    }
+
+    /// Minimize a target's corpus in place using libFuzzer's `-merge=1`
+    fn cmin_target<'a>(&self, args: &ArgMatches<'a>) -> Result<()> {
+        let target: String = args.value_of_os("TARGET").expect("TARGET is required").to_os_string()
+            .into_string().map_err(|_| "TARGET must be valid unicode")?;
+        let release: bool = args.is_present("release");
+        let assertions: bool = args.is_present("debug_assertions");
+        let sanitizer: &str = args.value_of("sanitizer").expect("no sanitizer");
+        let target_triple = args.value_of_os("TRIPLE").expect("no triple");
+        let cfg_fuzzing = !args.is_present("no_cfg_fuzzing");
+
+        let corpus_dir = self.corpus_for(&target, Engine::LibFuzzer)?;
+        let mut tmp_dir = corpus_dir.clone();
+        tmp_dir.set_file_name(format!("{}-cmin", target));
+        if tmp_dir.exists() {
+            fs::remove_dir_all(&tmp_dir)
+                .chain_err(|| format!("could not clear stale minimization directory at {:?}", tmp_dir))?;
+        }
+        fs::create_dir_all(&tmp_dir)
+            .chain_err(|| format!("could not make a minimization directory at {:?}", tmp_dir))?;
+
+        let mut cmd = self.build_libfuzzer(&target, release, assertions, sanitizer, cfg_fuzzing, target_triple)?;
+        cmd.arg("--");
+        cmd.arg("-merge=1");
+        cmd.arg(&tmp_dir);
+        cmd.arg(&corpus_dir);
+        let status = cmd.status().chain_err(|| format!("could not execute: {:?}", cmd))?;
+        if !status.success() {
+            return Err(format!("could not minimize corpus: {:?}", cmd).into());
+        }
+
+        // Swap the minimized corpus over the live one using two renames rather than a
+        // remove-then-rename, so the corpus is never missing for longer than an instant:
+        // a crash between the two renames still leaves both the old and new corpus on disk.
+        let mut old_dir = corpus_dir.clone();
+        old_dir.set_file_name(format!("{}-old", target));
+        if old_dir.exists() {
+            fs::remove_dir_all(&old_dir)
+                .chain_err(|| format!("could not clear stale corpus backup at {:?}", old_dir))?;
+        }
+        fs::rename(&corpus_dir, &old_dir)
+            .chain_err(|| format!("could not move the old corpus aside to {:?}", old_dir))?;
+        fs::rename(&tmp_dir, &corpus_dir)
+            .chain_err(|| format!("could not install the minimized corpus at {:?}", corpus_dir))?;
+        fs::remove_dir_all(&old_dir)
+            .chain_err(|| format!("could not remove the old corpus at {:?}", old_dir))?;
+        Ok(())
+    }
+
+    /// Shrink a single crashing input using libFuzzer's `-minimize_crash=1`
+    fn tmin_target<'a>(&self, args: &ArgMatches<'a>) -> Result<()> {
+        let target: String = args.value_of_os("TARGET").expect("TARGET is required").to_os_string()
+            .into_string().map_err(|_| "TARGET must be valid unicode")?;
+        let release: bool = args.is_present("release");
+        let assertions: bool = args.is_present("debug_assertions");
+        let sanitizer: &str = args.value_of("sanitizer").expect("no sanitizer");
+        let target_triple = args.value_of_os("TRIPLE").expect("no triple");
+        let artifact = args.value_of_os("ARTIFACT").expect("ARTIFACT is required");
+        let runs: u32 = args.value_of("runs").expect("no runs").parse()
+            .map_err(|_| "runs must be a valid integer")?;
+        let cfg_fuzzing = !args.is_present("no_cfg_fuzzing");
+
+        let artifact_name = path::Path::new(artifact).file_name()
+            .ok_or("ARTIFACT must be a file")?;
+        let mut minimized_path = self.crashes_for(&target, Engine::LibFuzzer)?;
+        minimized_path.push(format!("minimized-from-{}", artifact_name.to_string_lossy()));
+
+        let mut cmd = self.build_libfuzzer(&target, release, assertions, sanitizer, cfg_fuzzing, target_triple)?;
+        cmd.arg("--");
+        cmd.arg("-minimize_crash=1");
+        cmd.arg(format!("-runs={}", runs));
+        let mut exact_artifact_arg = ffi::OsString::from("-exact_artifact_path=");
+        exact_artifact_arg.push(&minimized_path);
+        cmd.arg(exact_artifact_arg);
+        cmd.arg(artifact);
+        let status = cmd.status().chain_err(|| format!("could not execute: {:?}", cmd))?;
+        if !status.success() {
+            return Err(format!("could not minimize crash: {:?}", cmd).into());
+        }
+        utils::print_message(&format!("minimized crash written to {:?}", minimized_path), term::color::GREEN);
+        Ok(())
+    }
+
+    /// Build a target and run it against exactly one crash or hang reproducer
+    fn reproduce_target<'a>(&self, args: &ArgMatches<'a>) -> Result<()> {
+        let target: String = args.value_of_os("TARGET").expect("TARGET is required").to_os_string()
+            .into_string().map_err(|_| "TARGET must be valid unicode")?;
+        let release: bool = args.is_present("release");
+        let assertions: bool = args.is_present("debug_assertions");
+        let sanitizer: &str = args.value_of("sanitizer").expect("no sanitizer");
+        let engine = Engine::from_arg(args.value_of("engine").expect("no engine"));
+        let target_triple = args.value_of_os("TRIPLE").expect("no triple");
+        let artifact = args.value_of_os("ARTIFACT").expect("ARTIFACT is required");
+        let cfg_fuzzing = !args.is_present("no_cfg_fuzzing");
+
+        let mut cmd = match engine {
+            Engine::LibFuzzer => {
+                let mut cmd = self.build_libfuzzer(&target, release, assertions, sanitizer, cfg_fuzzing, target_triple)?;
+                cmd.arg("--");
+                cmd.arg("-runs=1");
+                cmd.arg(artifact);
+                cmd
+            }
+            Engine::Honggfuzz => {
+                let rustflags = honggfuzz_rustflags(assertions, cfg_fuzzing);
+                self.build_instrumented(&target, release, target_triple, &[("RUSTFLAGS", rustflags.clone())])?;
+
+                let mut cmd = process::Command::new(self.bin_path(&target, target_triple, release));
+                cmd.env("RUSTFLAGS", &rustflags);
+                cmd.arg(artifact);
+                cmd
+            }
+        };
+        exec_cmd(&mut cmd).chain_err(|| format!("could not execute: {:?}", cmd))?;
+        Ok(())
+    }
+
+    fn coverage_for(&self, target: &str) -> Result<path::PathBuf> {
+        let mut p = self.path();
+        p.push("coverage");
+        p.push(target);
+        fs::create_dir_all(&p)
+            .chain_err(|| format!("could not make a coverage directory at {:?}", p))?;
+        Ok(p)
+    }
+
+    /// Path to the built target binary, as cargo lays it out under `--target <triple>`
+    fn bin_path(&self, target: &str, target_triple: &OsStr, release: bool) -> path::PathBuf {
+        self.path().join("target").join(target_triple)
+            .join(if release { "release" } else { "debug" })
+            .join(target)
+    }
+
     fn path(&self) -> path::PathBuf {
         self.root_project.join("fuzz")
     }
@@ -362,25 +883,61 @@ impl FuzzProject {
         self.path().join("Cargo.toml")
     }
 
-    fn corpus_for(&self, target: &str) -> Result<path::PathBuf> {
+    fn corpus_for(&self, target: &str, engine: Engine) -> Result<path::PathBuf> {
         let mut p = self.path();
-        p.push("corpus");
+        p.push(engine.subdir());
         p.push(target);
         fs::create_dir_all(&p)
             .chain_err(|| format!("could not make a corpus directory at {:?}", p))?;
         Ok(p)
     }
 
-    fn artifacts_for(&self, target: &str) -> Result<path::PathBuf> {
+    /// Directory libFuzzer/honggfuzz write crashing (and, for libFuzzer, OOM/leak) reproducers to
+    fn crashes_for(&self, target: &str, engine: Engine) -> Result<path::PathBuf> {
         let mut p = self.path();
-        p.push("artifacts");
+        p.push("crashes");
+        if engine == Engine::Honggfuzz {
+            p.push(engine.subdir());
+        }
         p.push(target);
         p.push(""); // trailing slash, necessary for libfuzzer, because it does simple concat
         fs::create_dir_all(&p)
-            .chain_err(|| format!("could not make a artifact directory at {:?}", p))?;
+            .chain_err(|| format!("could not make a crashes directory at {:?}", p))?;
         Ok(p)
     }
 
+    /// Directory hang (timeout) reproducers are triaged into
+    ///
+    /// libFuzzer only accepts a single `-artifact_prefix`, so timeout artifacts are written
+    /// into `crashes_for` alongside crashes during a run; anything there named `timeout-*`
+    /// is moved here once we get control back (see `exec_target`'s multi-job path), and
+    /// `list --hangs` also falls back to checking `crashes_for` for any stragglers.
+    fn hangs_for(&self, target: &str) -> Result<path::PathBuf> {
+        let mut p = self.path();
+        p.push("hangs");
+        p.push(target);
+        fs::create_dir_all(&p)
+            .chain_err(|| format!("could not make a hangs directory at {:?}", p))?;
+        Ok(p)
+    }
+
+    /// Move any `timeout-*` artifacts libFuzzer dropped into the crashes directory
+    /// over into `hangs_for`, where they belong
+    fn triage_hangs(&self, target: &str) -> Result<()> {
+        let crashes_dir = self.crashes_for(target, Engine::LibFuzzer)?;
+        let hangs_dir = self.hangs_for(target)?;
+        for entry in fs::read_dir(&crashes_dir)
+            .chain_err(|| format!("could not read directory {:?}", crashes_dir))? {
+            let entry = entry.chain_err(|| format!("could not read an entry of {:?}", crashes_dir))?;
+            let name = entry.file_name();
+            if name.to_string_lossy().starts_with("timeout-") {
+                fs::rename(entry.path(), hangs_dir.join(&name))
+                    .chain_err(|| format!("could not move {:?} into {:?}", entry.path(), hangs_dir))?;
+            }
+        }
+        Ok(())
+    }
+
     fn target_path(&self, target: &str) -> path::PathBuf {
         let mut root = self.path();
         root.push("fuzzers");
@@ -401,22 +958,6 @@ impl FuzzProject {
         )
     }
 
-    fn root_project_name(&self) -> Result<String> {
-        let filename = self.root_project.join("Cargo.toml");
-        let mut file = fs::File::open(&filename)?;
-        let mut data = Vec::new();
-        file.read_to_end(&mut data)?;
-        let value: toml::Value = toml::from_slice(&data)?;
-        let name = value.as_table().and_then(|v| v.get("package"))
-                                   .and_then(toml::Value::as_table)
-                                   .and_then(|v| v.get("name"))
-                                   .and_then(toml::Value::as_str);
-        if let Some(name) = name {
-            Ok(String::from(name))
-        } else {
-            Err(format!("{:?} (package.name) is malformed", filename).into())
-        }
-    }
 }
 
 fn collect_targets(value: &toml::Value) -> Vec<String> {
@@ -441,24 +982,25 @@ fn is_fuzz_manifest(value: &toml::Value) -> bool {
     is_fuzz == Some(true)
 }
 
-/// Returns the path for the first found non-fuzz Cargo package
-fn find_package() -> Result<path::PathBuf> {
+/// Walk up from the current directory to find the nearest `Cargo.toml` that
+/// isn't itself a cargo-fuzz manifest
+///
+/// `cargo fuzz` is commonly invoked from inside `fuzz/`, whose generated manifest
+/// has its own `[workspace]` table to exclude itself from the parent project; plain
+/// Cargo manifest discovery would stop there and never see the real project. Skip
+/// over any fuzz manifests on the way up so `cargo_metadata` gets pointed at the
+/// project being fuzzed, not the fuzz crate itself.
+fn nearest_non_fuzz_manifest() -> Result<path::PathBuf> {
     let mut dir = env::current_dir()?;
-    let mut data = Vec::new();
     loop {
         let manifest_path = dir.join("Cargo.toml");
-        match fs::File::open(&manifest_path) {
-            Err(_) => {},
-            Ok(mut f) => {
-                f.read_to_end(&mut data)?;
-                let value: toml::Value = toml::from_slice(&data)
-                    .chain_err(||
-                        format!("could not decode the manifest file at {:?}", manifest_path)
-                    )?;
-                if !is_fuzz_manifest(&value) {
-                    // Not a cargo-fuzz project => must be a proper cargo project :)
-                    return Ok(dir);
-                }
+        if let Ok(mut f) = fs::File::open(&manifest_path) {
+            let mut data = Vec::new();
+            f.read_to_end(&mut data)?;
+            let value: toml::Value = toml::from_slice(&data)
+                .chain_err(|| format!("could not decode the manifest file at {:?}", manifest_path))?;
+            if !is_fuzz_manifest(&value) {
+                return Ok(manifest_path);
             }
         }
         if !dir.pop() { break; }
@@ -466,6 +1008,45 @@ fn find_package() -> Result<path::PathBuf> {
     Err("could not find a cargo project".into())
 }
 
+/// Resolve the directory and name of the package to fuzz via `cargo metadata`
+///
+/// If `package` is given, the workspace member of that name is selected. Otherwise, if
+/// there's a single workspace member, it's used; if there are several, the member whose
+/// manifest directory contains the current directory is selected, erroring out if that's
+/// ambiguous too.
+fn find_package(package: Option<&str>) -> Result<(path::PathBuf, String)> {
+    let manifest_path = nearest_non_fuzz_manifest()?;
+    let metadata = cargo_metadata::MetadataCommand::new().manifest_path(&manifest_path).no_deps().exec()
+        .chain_err(|| "could not run `cargo metadata`; are you inside a cargo project?")?;
+
+    let members: Vec<_> = metadata.packages.iter()
+        .filter(|p| metadata.workspace_members.contains(&p.id))
+        .collect();
+
+    let selected = if let Some(package) = package {
+        members.iter().find(|p| p.name == package).cloned()
+            .ok_or_else(|| format!("no package named {:?} in this workspace", package))?
+    } else if members.len() == 1 {
+        members[0]
+    } else {
+        let cwd = env::current_dir()?;
+        let mut containing = members.iter().filter(|p| {
+            p.manifest_path.parent().map_or(false, |dir| cwd.starts_with(dir))
+        });
+        match (containing.next(), containing.next()) {
+            (Some(p), None) => *p,
+            _ => return Err(
+                "this is a workspace with multiple members; use --package to pick one to fuzz".into()
+            ),
+        }
+    };
+
+    let root_project = selected.manifest_path.parent()
+        .ok_or_else(|| format!("manifest {:?} has no parent directory", selected.manifest_path))?
+        .to_path_buf();
+    Ok((root_project, selected.name.clone()))
+}
+
 #[cfg(unix)]
 fn exec_cmd(cmd: &mut process::Command) -> Result<process::ExitStatus> {
     use std::os::unix::process::CommandExt;